@@ -1,92 +1,605 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::SystemTime;
 use serde::{Deserialize, Serialize};
+use tauri::Emitter;
 
 #[derive(Serialize, Deserialize)]
 pub struct FileMetadata {
     pub modified_time: u64,
     pub size: u64,
     pub exists: bool,
+    pub hash: Option<String>,
+}
+
+/// Files larger than this are not hashed by default, to avoid reading huge
+/// files just to answer "did this change?".
+const DEFAULT_HASH_SIZE_THRESHOLD: u64 = 10 * 1024 * 1024;
+
+/// Errors a command can return, crossing the Tauri boundary as a tagged
+/// `{ kind, message }` object so the frontend can branch on `kind` instead
+/// of pattern-matching formatted English.
+#[derive(Debug, thiserror::Error)]
+pub enum CommandError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    #[error("File is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("Backup failed: {0}")]
+    BackupFailed(String),
+    #[error("Unsupported platform")]
+    UnsupportedPlatform,
+}
+
+impl Serialize for CommandError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        let kind = match self {
+            CommandError::Io(_) => "Io",
+            CommandError::NotFound(_) => "NotFound",
+            CommandError::PermissionDenied(_) => "PermissionDenied",
+            CommandError::InvalidUtf8 => "InvalidUtf8",
+            CommandError::BackupFailed(_) => "BackupFailed",
+            CommandError::UnsupportedPlatform => "UnsupportedPlatform",
+        };
+
+        let mut state = serializer.serialize_struct("CommandError", 2)?;
+        state.serialize_field("kind", kind)?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+/// Classify a raw I/O error against `path` into the more specific
+/// `CommandError` variants the frontend can branch on.
+fn classify_io_error(path: &str, err: std::io::Error) -> CommandError {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => CommandError::NotFound(path.to_string()),
+        std::io::ErrorKind::PermissionDenied => CommandError::PermissionDenied(path.to_string()),
+        std::io::ErrorKind::InvalidData => CommandError::InvalidUtf8,
+        _ => CommandError::Io(err),
+    }
 }
 
 /// Read a config file from the filesystem
 #[tauri::command]
-fn read_config_file(path: String) -> Result<String, String> {
-    fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file {}: {}", path, e))
+fn read_config_file(path: String) -> Result<String, CommandError> {
+    fs::read_to_string(&path).map_err(|e| classify_io_error(&path, e))
+}
+
+/// One `key = value` setting from a resolved config tree, tagged with the
+/// file it was assigned in.
+#[derive(Serialize, Deserialize)]
+pub struct EffectiveSetting {
+    pub key: String,
+    pub value: String,
+    pub source_file: String,
+}
+
+/// The raw text of one file in a resolved config tree.
+#[derive(Serialize, Deserialize)]
+pub struct ConfigFileContent {
+    pub path: String,
+    pub content: String,
+}
+
+/// A Ghostty config plus everything it pulls in via `config-file` includes.
+#[derive(Serialize, Deserialize)]
+pub struct ConfigTree {
+    pub files: Vec<ConfigFileContent>,
+    pub effective_settings: Vec<EffectiveSetting>,
+}
+
+/// Read `path` and follow its `config-file` includes, returning the raw
+/// text of every file involved plus a flattened, last-wins view of the
+/// effective settings.
+///
+/// Includes are resolved relative to the directory of the file that
+/// references them (with `~` expanded to the home directory), and a
+/// visited-set of canonicalized paths guards against include cycles.
+#[tauri::command]
+fn read_config_tree(path: String) -> Result<ConfigTree, CommandError> {
+    let mut files = Vec::new();
+    let mut raw_settings = Vec::new();
+    let mut visited = std::collections::HashSet::new();
+
+    load_config_tree(Path::new(&path), &mut visited, &mut files, &mut raw_settings)?;
+
+    Ok(ConfigTree {
+        files,
+        effective_settings: flatten_last_wins(raw_settings),
+    })
+}
+
+fn load_config_tree(
+    path: &Path,
+    visited: &mut std::collections::HashSet<PathBuf>,
+    files: &mut Vec<ConfigFileContent>,
+    settings: &mut Vec<EffectiveSetting>,
+) -> Result<(), CommandError> {
+    let source_file = path.to_string_lossy().to_string();
+    let canonical = fs::canonicalize(path).map_err(|e| classify_io_error(&source_file, e))?;
+
+    if !visited.insert(canonical.clone()) {
+        // Already processed this file somewhere in the tree (an include
+        // cycle, or the same file included more than once).
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&canonical).map_err(|e| classify_io_error(&source_file, e))?;
+    let dir = canonical.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("."));
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim();
+
+        if key == "config-file" {
+            // Ghostty tolerates missing includes, and supports an explicit
+            // `?<path>` optional-include spelling; strip the `?` before
+            // resolving, then swallow a NotFound from either form rather
+            // than aborting the whole tree over one stale reference.
+            let include_value = value.strip_prefix('?').map(str::trim).unwrap_or(value);
+            let included = resolve_include_path(include_value, &dir);
+            match load_config_tree(&included, visited, files, settings) {
+                Ok(()) | Err(CommandError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
+        } else {
+            settings.push(EffectiveSetting {
+                key: key.to_string(),
+                value: value.to_string(),
+                source_file: source_file.clone(),
+            });
+        }
+    }
+
+    files.push(ConfigFileContent {
+        path: source_file,
+        content,
+    });
+
+    Ok(())
+}
+
+/// Resolve a `config-file` directive's value against the directory of the
+/// file that referenced it, expanding a leading `~/`.
+fn resolve_include_path(raw: &str, including_dir: &Path) -> PathBuf {
+    let expanded = match raw.strip_prefix("~/") {
+        Some(rest) => std::env::var("HOME")
+            .map(|home| PathBuf::from(home).join(rest))
+            .unwrap_or_else(|_| PathBuf::from(raw)),
+        None => PathBuf::from(raw),
+    };
+
+    if expanded.is_absolute() {
+        expanded
+    } else {
+        including_dir.join(expanded)
+    }
+}
+
+/// Collapse settings into last-wins order, preserving each key's first
+/// position (an RFC 7396-style merge applied to the flat keyspace).
+fn flatten_last_wins(entries: Vec<EffectiveSetting>) -> Vec<EffectiveSetting> {
+    let mut order = Vec::new();
+    let mut latest: std::collections::HashMap<String, EffectiveSetting> = std::collections::HashMap::new();
+
+    for entry in entries {
+        if !latest.contains_key(&entry.key) {
+            order.push(entry.key.clone());
+        }
+        latest.insert(entry.key.clone(), entry);
+    }
+
+    order.into_iter().filter_map(|key| latest.remove(&key)).collect()
 }
 
 /// Write content to a config file
+///
+/// Writes are atomic: the new content lands in a temp file next to the
+/// destination, is fsync'd, and is then renamed over the destination so a
+/// reader never observes a truncated file even if we crash mid-write.
 #[tauri::command]
-fn write_config_file(path: String, content: String) -> Result<(), String> {
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to write file {}: {}", path, e))
+fn write_config_file(path: String, content: String) -> Result<(), CommandError> {
+    atomic_write(Path::new(&path), content.as_bytes()).map_err(|e| classify_io_error(&path, e))
+}
+
+/// Monotonic counter mixed into temp file names so concurrent `atomic_write`
+/// calls on different threads of the same process (e.g. a save racing a
+/// backup restore) never pick the same temp path.
+static TMP_FILE_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Atomically replace `path` with `content`.
+///
+/// The temp file is created beside `path` (not in the system temp dir)
+/// because `path`'s directory may live on a different mount, which would
+/// make the final `rename` fail with a cross-device error. If that still
+/// happens (e.g. `path` itself is on another mount from its own parent via
+/// a bind mount), fall back to a plain copy-then-remove.
+fn atomic_write(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "config".to_string());
+    let unique = TMP_FILE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let tmp_path = dir.join(format!("{}.tmp.{}.{}", file_name, std::process::id(), unique));
+
+    // Preserve the destination's existing permissions (e.g. a 0600 config)
+    // instead of letting the temp file's default mode win on rename.
+    let existing_permissions = fs::metadata(path).ok().map(|m| m.permissions());
+
+    let write_result = (|| -> std::io::Result<()> {
+        let mut tmp_file = fs::File::create(&tmp_path)?;
+        tmp_file.write_all(content)?;
+        if let Some(permissions) = existing_permissions {
+            tmp_file.set_permissions(permissions)?;
+        }
+        tmp_file.sync_all()
+    })();
+
+    if let Err(e) = write_result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        if !is_cross_device_error(&e) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        let copy_result = fs::copy(&tmp_path, path).and_then(|_| fs::remove_file(&tmp_path));
+        if let Err(copy_err) = copy_result {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(copy_err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `err` is the platform's "rename across filesystems" error.
+fn is_cross_device_error(err: &std::io::Error) -> bool {
+    match err.raw_os_error() {
+        #[cfg(unix)]
+        Some(code) => code == 18, // EXDEV
+        #[cfg(windows)]
+        Some(code) => code == 17, // ERROR_NOT_SAME_DEVICE
+        #[cfg(not(any(unix, windows)))]
+        Some(_) => false,
+        None => false,
+    }
 }
 
 /// Get file metadata (for change detection)
+///
+/// `hash_size_threshold` caps how large a file we'll read in order to hash
+/// it (default `DEFAULT_HASH_SIZE_THRESHOLD`); files above it get `hash:
+/// None` rather than paying for a full read on every poll.
 #[tauri::command]
-fn get_file_metadata(path: String) -> Result<FileMetadata, String> {
-    match fs::metadata(&path) {
-        Ok(metadata) => {
-            let modified_time = metadata
-                .modified()
-                .map_err(|e| format!("Failed to get modified time: {}", e))?
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .map_err(|e| format!("Invalid system time: {}", e))?
-                .as_secs();
-
-            Ok(FileMetadata {
-                modified_time,
-                size: metadata.len(),
-                exists: true,
-            })
-        }
-        Err(_) => Ok(FileMetadata {
+fn get_file_metadata(path: String, hash_size_threshold: Option<u64>) -> Result<FileMetadata, CommandError> {
+    Ok(compute_file_metadata(&path, hash_size_threshold.unwrap_or(DEFAULT_HASH_SIZE_THRESHOLD)))
+}
+
+/// Compute `path`'s metadata, falling back to an `exists: false` record
+/// rather than erroring when the file is missing. Shared by
+/// `get_file_metadata` and the `watch_config_file` change notifier.
+fn compute_file_metadata(path: &str, hash_size_threshold: u64) -> FileMetadata {
+    let Ok(metadata) = fs::metadata(path) else {
+        return FileMetadata {
             modified_time: 0,
             size: 0,
             exists: false,
-        }),
+            hash: None,
+        };
+    };
+
+    let modified_time = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let size = metadata.len();
+    let hash = if size <= hash_size_threshold {
+        fs::read(path).ok().map(|bytes| blake3::hash(&bytes).to_hex().to_string())
+    } else {
+        None
+    };
+
+    FileMetadata {
+        modified_time,
+        size,
+        exists: true,
+        hash,
     }
 }
 
-/// Create a backup of a file
+/// How long a watch has to stay quiet before a debounced burst is flushed
+/// as a single emission.
+const WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Active `notify` watchers, keyed by the path they were started for, so
+/// `unwatch_config_file` can find and drop the right one.
+#[derive(Default)]
+pub struct WatcherRegistry(Mutex<std::collections::HashMap<String, notify::RecommendedWatcher>>);
+
+/// Watch `path` for external changes and emit a `config-file-changed`
+/// event (carrying the file's new `FileMetadata`) whenever it's modified,
+/// created, or deleted.
+///
+/// We watch `path`'s parent directory rather than the file itself:
+/// watching the file directly follows its inode, so an atomic
+/// rename-over (exactly what our own `atomic_write`, and editors like
+/// vim, do to save) leaves the watch on an unlinked inode and it goes
+/// silent after the first change. Directory events are filtered down to
+/// `path`'s filename.
+///
+/// Bursts of events for the same save (e.g. remove+create+rename) are
+/// debounced on the trailing edge: each event schedules an emission after
+/// a quiet period, and a newer event cancels the pending one, so the
+/// metadata reported always reflects the file's settled state rather than
+/// a snapshot from mid-burst.
 #[tauri::command]
-fn create_backup(path: String) -> Result<String, String> {
-    let backup_path = format!("{}.bak", path);
+fn watch_config_file(
+    app: tauri::AppHandle,
+    watchers: tauri::State<WatcherRegistry>,
+    path: String,
+) -> Result<(), CommandError> {
+    use notify::Watcher;
+
+    let target = PathBuf::from(&path);
+    let target_file_name = target.file_name().map(|n| n.to_os_string());
+    let watch_dir = target.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf();
+
+    let event_path = path.clone();
+    let generation = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
 
-    // Check if source file exists
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else { return };
+        if !matches!(
+            event.kind,
+            notify::EventKind::Modify(_) | notify::EventKind::Create(_) | notify::EventKind::Remove(_)
+        ) {
+            return;
+        }
+        if !event.paths.iter().any(|p| p.file_name() == target_file_name.as_deref()) {
+            return;
+        }
+
+        let my_generation = generation.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        let generation = generation.clone();
+        let event_path = event_path.clone();
+        let app = app.clone();
+
+        std::thread::spawn(move || {
+            std::thread::sleep(WATCH_DEBOUNCE);
+            if generation.load(std::sync::atomic::Ordering::SeqCst) != my_generation {
+                // A newer event arrived during the quiet period; its own
+                // timer will flush instead.
+                return;
+            }
+
+            let metadata = compute_file_metadata(&event_path, DEFAULT_HASH_SIZE_THRESHOLD);
+            let _ = app.emit("config-file-changed", metadata);
+        });
+    })
+    .map_err(|e| CommandError::Io(std::io::Error::other(e.to_string())))?;
+
+    watcher
+        .watch(&watch_dir, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| classify_watch_error(&path, e))?;
+
+    let mut registry = watchers
+        .0
+        .lock()
+        .map_err(|e| CommandError::Io(std::io::Error::other(e.to_string())))?;
+    registry.insert(path, watcher);
+
+    Ok(())
+}
+
+/// Classify a `notify` watch error against `path`, falling back to the
+/// generic `Io` variant when the underlying cause isn't more specific.
+fn classify_watch_error(path: &str, err: notify::Error) -> CommandError {
+    match err.kind {
+        notify::ErrorKind::PathNotFound => CommandError::NotFound(path.to_string()),
+        _ => CommandError::Io(std::io::Error::other(err.to_string())),
+    }
+}
+
+/// Stop watching `path` for external changes.
+#[tauri::command]
+fn unwatch_config_file(watchers: tauri::State<WatcherRegistry>, path: String) -> Result<(), CommandError> {
+    let mut registry = watchers
+        .0
+        .lock()
+        .map_err(|e| CommandError::Io(std::io::Error::other(e.to_string())))?;
+    registry.remove(&path);
+    Ok(())
+}
+
+/// Default number of rotating backups to keep per file.
+const DEFAULT_BACKUP_RETENTION: usize = 10;
+
+/// One rotating backup snapshot, as surfaced to the frontend's version
+/// history UI.
+#[derive(Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub backup_path: String,
+    pub modified_time: u64,
+    pub size: u64,
+}
+
+/// Create a timestamped backup of a file, pruning older snapshots beyond
+/// `retention` (default `DEFAULT_BACKUP_RETENTION`).
+#[tauri::command]
+fn create_backup(path: String, retention: Option<usize>) -> Result<String, CommandError> {
     if !PathBuf::from(&path).exists() {
-        return Err(format!("Source file does not exist: {}", path));
+        return Err(CommandError::NotFound(path));
+    }
+
+    // Nanosecond resolution makes same-name collisions from two saves in
+    // close succession vanishingly unlikely, but we still disambiguate
+    // explicitly rather than let `fs::copy` clobber an existing snapshot.
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|e| CommandError::BackupFailed(format!("Invalid system time: {}", e)))?
+        .as_nanos();
+
+    let mut backup_path = format!("{}.{}.bak", path, timestamp);
+    let mut disambiguator = 1u32;
+    while PathBuf::from(&backup_path).exists() {
+        backup_path = format!("{}.{}-{}.bak", path, timestamp, disambiguator);
+        disambiguator += 1;
     }
 
     fs::copy(&path, &backup_path)
-        .map_err(|e| format!("Failed to create backup: {}", e))?;
+        .map_err(|e| CommandError::BackupFailed(format!("Failed to create backup: {}", e)))?;
+
+    prune_old_backups(&path, retention.unwrap_or(DEFAULT_BACKUP_RETENTION))?;
 
     Ok(backup_path)
 }
 
+/// List the rotating backups for `path`, newest first.
+#[tauri::command]
+fn list_backups(path: String) -> Result<Vec<BackupInfo>, CommandError> {
+    let mut backups = collect_backups(&path)?;
+    backups.sort_by(|a, b| b.modified_time.cmp(&a.modified_time));
+    Ok(backups)
+}
+
+/// Atomically restore a chosen backup snapshot over `target_path`.
+#[tauri::command]
+fn restore_backup(backup_path: String, target_path: String) -> Result<(), CommandError> {
+    let content = fs::read(&backup_path).map_err(|e| classify_io_error(&backup_path, e))?;
+
+    atomic_write(Path::new(&target_path), &content).map_err(|e| classify_io_error(&target_path, e))
+}
+
+/// Find every `<path>.<unix_ts>[-<n>].bak` snapshot belonging to `path`.
+///
+/// The segment between the filename prefix and the `.bak` suffix must be
+/// all-digits (optionally `<digits>-<digits>` for same-instant
+/// disambiguation) so that e.g. `list_backups("config")` doesn't pick up
+/// `config.local.<ts>.bak`, a sibling file that merely shares a prefix.
+fn collect_backups(path: &str) -> Result<Vec<BackupInfo>, CommandError> {
+    let target = PathBuf::from(path);
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| CommandError::BackupFailed(format!("Invalid path: {}", path)))?
+        .to_string_lossy()
+        .to_string();
+    let dir = target
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let prefix = format!("{}.", file_name);
+    let suffix = ".bak";
+
+    let entries = fs::read_dir(&dir).map_err(|e| classify_io_error(&dir.to_string_lossy(), e))?;
+
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(timestamp_segment) = name.strip_prefix(&prefix).and_then(|rest| rest.strip_suffix(suffix)) else {
+            continue;
+        };
+        if !is_backup_timestamp_segment(timestamp_segment) {
+            continue;
+        }
+
+        let metadata = entry.metadata().map_err(|e| classify_io_error(&name, e))?;
+        let modified_time = metadata
+            .modified()?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|e| CommandError::BackupFailed(format!("Invalid system time: {}", e)))?
+            .as_secs();
+
+        backups.push(BackupInfo {
+            backup_path: entry.path().to_string_lossy().to_string(),
+            modified_time,
+            size: metadata.len(),
+        });
+    }
+
+    Ok(backups)
+}
+
+/// Whether `segment` is a bare timestamp (`<digits>`) or a disambiguated
+/// one (`<digits>-<digits>`), as produced by `create_backup`.
+fn is_backup_timestamp_segment(segment: &str) -> bool {
+    match segment.split_once('-') {
+        Some((timestamp, disambiguator)) => {
+            !timestamp.is_empty()
+                && !disambiguator.is_empty()
+                && timestamp.bytes().all(|b| b.is_ascii_digit())
+                && disambiguator.bytes().all(|b| b.is_ascii_digit())
+        }
+        None => !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()),
+    }
+}
+
+/// Remove all but the `retention` most recently modified backups of `path`.
+fn prune_old_backups(path: &str, retention: usize) -> Result<(), CommandError> {
+    let mut backups = collect_backups(path)?;
+    backups.sort_by(|a, b| b.modified_time.cmp(&a.modified_time));
+
+    for stale in backups.into_iter().skip(retention) {
+        let _ = fs::remove_file(&stale.backup_path);
+    }
+
+    Ok(())
+}
+
 /// Check if a file exists
 #[tauri::command]
 fn file_exists(path: String) -> bool {
     PathBuf::from(&path).exists()
 }
 
-/// Get the default config file path for the current platform
+/// Get the default config file path for the current platform, unless the
+/// user has overridden it in their app settings.
 #[tauri::command]
-fn get_default_config_path() -> Result<String, String> {
+fn get_default_config_path(app_config: tauri::State<Mutex<AppConfig>>) -> Result<String, CommandError> {
+    if let Some(custom_path) = app_config
+        .lock()
+        .map_err(|e| CommandError::Io(std::io::Error::other(e.to_string())))?
+        .custom_config_path
+        .clone()
+    {
+        return Ok(custom_path);
+    }
+
     #[cfg(target_os = "macos")]
     {
-        let home = std::env::var("HOME")
-            .map_err(|_| "Could not determine home directory".to_string())?;
+        let home = std::env::var("HOME").map_err(|_| CommandError::NotFound("HOME".to_string()))?;
         Ok(format!("{}/.config/ghostty/config", home))
     }
 
     #[cfg(target_os = "linux")]
     {
         let config_home = std::env::var("XDG_CONFIG_HOME").ok();
-        let home = std::env::var("HOME")
-            .map_err(|_| "Could not determine home directory".to_string())?;
+        let home = std::env::var("HOME").map_err(|_| CommandError::NotFound("HOME".to_string()))?;
 
         let base = config_home.unwrap_or_else(|| format!("{}/.config", home));
         Ok(format!("{}/ghostty/config", base))
@@ -94,29 +607,193 @@ fn get_default_config_path() -> Result<String, String> {
 
     #[cfg(target_os = "windows")]
     {
-        let app_data = std::env::var("APPDATA")
-            .map_err(|_| "Could not determine AppData directory".to_string())?;
+        let app_data = std::env::var("APPDATA").map_err(|_| CommandError::NotFound("APPDATA".to_string()))?;
         Ok(format!("{}\\ghostty\\config", app_data))
     }
 
     #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
     {
-        Err("Unsupported platform".to_string())
+        Err(CommandError::UnsupportedPlatform)
     }
 }
 
+/// Persistent application preferences: recent files, custom search paths,
+/// and editor settings. Loaded via `confy` on startup and kept in sync
+/// between disk and the in-memory `tauri::State`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct AppConfig {
+    pub last_opened_path: Option<String>,
+    pub recent_paths: Vec<String>,
+    pub extra_config_search_paths: Vec<String>,
+    pub custom_config_path: Option<String>,
+    pub backup_retention: usize,
+    pub editor_font: Option<String>,
+}
+
+/// Get the current application settings.
+#[tauri::command]
+fn get_app_settings(app_config: tauri::State<Mutex<AppConfig>>) -> Result<AppConfig, CommandError> {
+    app_config
+        .lock()
+        .map(|settings| settings.clone())
+        .map_err(|e| CommandError::Io(std::io::Error::other(e.to_string())))
+}
+
+/// Persist new application settings to disk and update the in-memory state.
+#[tauri::command]
+fn set_app_settings(app_config: tauri::State<Mutex<AppConfig>>, settings: AppConfig) -> Result<(), CommandError> {
+    confy::store("ghostty_config", None, &settings)
+        .map_err(|e| CommandError::Io(std::io::Error::other(e.to_string())))?;
+
+    let mut current = app_config
+        .lock()
+        .map_err(|e| CommandError::Io(std::io::Error::other(e.to_string())))?;
+    *current = settings;
+
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let app_config: AppConfig = confy::load("ghostty_config", None).unwrap_or_default();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .manage(Mutex::new(app_config))
+        .manage(WatcherRegistry::default())
         .invoke_handler(tauri::generate_handler![
             read_config_file,
+            read_config_tree,
             write_config_file,
             get_file_metadata,
             create_backup,
+            list_backups,
+            restore_backup,
             file_exists,
             get_default_config_path,
+            get_app_settings,
+            set_app_settings,
+            watch_config_file,
+            unwatch_config_file,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_write_round_trips_and_preserves_permissions() {
+        let dir = std::env::temp_dir().join(format!("ghostty_config_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("config");
+
+        atomic_write(&target, b"initial").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+
+        atomic_write(&target, b"updated").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "updated");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&target).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode, 0o600);
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn tmp_file_counter_hands_out_distinct_values() {
+        let first = TMP_FILE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let second = TMP_FILE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn flatten_last_wins_keeps_first_key_position_with_latest_value() {
+        let entries = vec![
+            EffectiveSetting { key: "font-size".into(), value: "10".into(), source_file: "a".into() },
+            EffectiveSetting { key: "theme".into(), value: "dark".into(), source_file: "a".into() },
+            EffectiveSetting { key: "font-size".into(), value: "14".into(), source_file: "b".into() },
+        ];
+
+        let flattened = flatten_last_wins(entries);
+
+        assert_eq!(flattened.len(), 2);
+        assert_eq!(flattened[0].key, "font-size");
+        assert_eq!(flattened[0].value, "14");
+        assert_eq!(flattened[0].source_file, "b");
+        assert_eq!(flattened[1].key, "theme");
+        assert_eq!(flattened[1].value, "dark");
+    }
+
+    #[test]
+    fn resolve_include_path_expands_home_and_relative_paths() {
+        let including_dir = Path::new("/home/user/.config/ghostty");
+
+        assert_eq!(
+            resolve_include_path("themes/dark", including_dir),
+            including_dir.join("themes/dark")
+        );
+        assert_eq!(
+            resolve_include_path("/etc/ghostty/config", including_dir),
+            PathBuf::from("/etc/ghostty/config")
+        );
+
+        std::env::set_var("HOME", "/home/user");
+        assert_eq!(
+            resolve_include_path("~/custom-config", including_dir),
+            PathBuf::from("/home/user/custom-config")
+        );
+    }
+
+    #[test]
+    fn is_backup_timestamp_segment_rejects_sibling_filenames() {
+        // "config.local" sharing the "config." prefix must not be mistaken
+        // for a backup of "config": the segment here would be "local",
+        // which isn't all-digits.
+        assert!(!is_backup_timestamp_segment("local"));
+        assert!(is_backup_timestamp_segment("1690000000000000000"));
+        assert!(is_backup_timestamp_segment("1690000000000000000-1"));
+        assert!(!is_backup_timestamp_segment(""));
+        assert!(!is_backup_timestamp_segment("1690000000000000000-"));
+    }
+
+    #[test]
+    fn collect_backups_ignores_sibling_file_with_shared_prefix() {
+        let dir = std::env::temp_dir().join(format!("ghostty_config_backups_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config");
+
+        fs::write(&config_path, b"content").unwrap();
+        fs::write(dir.join("config.1700000000000000000.bak"), b"backup").unwrap();
+        fs::write(dir.join("config.local.1700000000000000000.bak"), b"sibling backup").unwrap();
+
+        let backups = collect_backups(config_path.to_str().unwrap()).unwrap();
+
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].backup_path.ends_with("config.1700000000000000000.bak"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn is_cross_device_error_matches_only_exdev() {
+        #[cfg(unix)]
+        {
+            let exdev = std::io::Error::from_raw_os_error(18);
+            assert!(is_cross_device_error(&exdev));
+        }
+
+        let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert!(!is_cross_device_error(&not_found));
+    }
+}